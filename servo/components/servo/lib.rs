@@ -21,6 +21,7 @@ extern crate env_logger;
 #[cfg(all(not(target_os = "windows"), not(target_os = "ios")))]
 extern crate gaol;
 extern crate gleam;
+#[macro_use]
 extern crate log;
 
 pub extern crate bluetooth;
@@ -97,8 +98,10 @@ use servo_config::resource_files::resources_dir_path;
 use std::borrow::Cow;
 use std::cmp::max;
 use std::path::PathBuf;
+use std::panic;
 use std::rc::Rc;
-use std::sync::mpsc::{Sender, channel};
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::thread;
 use webrender::RendererKind;
 use webvr::{WebVRThread, WebVRCompositorHandler};
 
@@ -107,6 +110,42 @@ pub use servo_config as config;
 pub use servo_url as url;
 pub use msg::constellation_msg::TopLevelBrowsingContextId as BrowserId;
 
+/// Messages from the engine to the embedding application, delivered through
+/// the dedicated `EmbedderProxy`/`EmbedderReceiver` channel rather than
+/// mixed in with compositor repaint traffic.
+pub enum EmbedderMsg {
+    /// A thread in this process panicked, as captured by the panic hook
+    /// installed in `Servo::new` and sent directly through the
+    /// `EmbedderProxy`. Carries the panicking thread's name and the panic
+    /// message, so an embedder can show a "this tab has crashed" page
+    /// instead of losing the process silently. Content processes have no
+    /// `EmbedderProxy` of their own; their panics go through `error!()`
+    /// and the constellation's logging path instead (see
+    /// `install_crash_reporter`).
+    Panic(String, String),
+}
+
+/// A proxy for the embedder to receive `EmbedderMsg`s from the engine,
+/// analogous to `CompositorProxy` but for user-visible, non-rendering
+/// messages rather than compositor repaint traffic.
+#[derive(Clone)]
+pub struct EmbedderProxy {
+    sender: Sender<(Option<BrowserId>, EmbedderMsg)>,
+    event_loop_waker: Box<compositor_thread::EventLoopWaker>,
+}
+
+impl EmbedderProxy {
+    pub fn send(&self, msg: (Option<BrowserId>, EmbedderMsg)) {
+        self.sender.send(msg).unwrap();
+        self.event_loop_waker.wake();
+    }
+}
+
+/// The embedder's endpoint for the `EmbedderProxy` channel.
+pub struct EmbedderReceiver {
+    receiver: Receiver<(Option<BrowserId>, EmbedderMsg)>,
+}
+
 /// The in-process interface to Servo.
 ///
 /// It does everything necessary to render the web, primarily
@@ -121,6 +160,7 @@ pub use msg::constellation_msg::TopLevelBrowsingContextId as BrowserId;
 pub struct Servo<Window: WindowMethods + 'static> {
     compositor: IOCompositor<Window>,
     constellation_chan: Sender<ConstellationMsg>,
+    embedder_receiver: EmbedderReceiver,
 }
 
 impl<Window> Servo<Window> where Window: WindowMethods + 'static {
@@ -137,6 +177,18 @@ impl<Window> Servo<Window> where Window: WindowMethods + 'static {
         // to deliver the message.
         let (compositor_proxy, compositor_receiver) =
             create_compositor_channel(window.create_event_loop_waker());
+
+        // A separate channel for user-visible engine-to-app messages, kept
+        // distinct from the compositor proxy above so that embedders can
+        // poll them without wading through repaint traffic.
+        let (embedder_proxy, embedder_receiver) =
+            create_embedder_channel(window.create_event_loop_waker());
+
+        // Make sure panics in any thread of this process are reported to
+        // the embedder as a crash, now that we have the `EmbedderProxy` to
+        // report them through.
+        install_crash_reporter(Some(embedder_proxy.clone()));
+
         let supports_clipboard = window.supports_clipboard();
         let time_profiler_chan = profile_time::Profiler::create(&opts.time_profiling,
                                                                 opts.time_profiler_trace_path.clone());
@@ -205,17 +257,18 @@ impl<Window> Servo<Window> where Window: WindowMethods + 'static {
         // Create the constellation, which maintains the engine
         // pipelines, including the script and layout threads, as well
         // as the navigation context.
-        let (constellation_chan, sw_senders) = create_constellation(opts.user_agent.clone(),
-                                                                    opts.config_dir.clone(),
-                                                                    compositor_proxy.clone_compositor_proxy(),
-                                                                    time_profiler_chan.clone(),
-                                                                    mem_profiler_chan.clone(),
-                                                                    debugger_chan,
-                                                                    devtools_chan,
-                                                                    supports_clipboard,
-                                                                    &mut webrender,
-                                                                    webrender_document,
-                                                                    webrender_api_sender);
+        let (constellation_chan, sw_senders) =
+            create_constellation(opts.user_agent.clone(),
+                                opts.config_dir.clone(),
+                                compositor_proxy.clone_compositor_proxy(),
+                                time_profiler_chan.clone(),
+                                mem_profiler_chan.clone(),
+                                debugger_chan,
+                                devtools_chan,
+                                supports_clipboard,
+                                &mut webrender,
+                                webrender_document,
+                                webrender_api_sender);
 
         // Send the constellation's swmanager sender to service worker manager thread
         script::init_service_workers(sw_senders);
@@ -242,9 +295,16 @@ impl<Window> Servo<Window> where Window: WindowMethods + 'static {
         Servo {
             compositor: compositor,
             constellation_chan: constellation_chan,
+            embedder_receiver: embedder_receiver,
         }
     }
 
+    /// Drain the queue of pending engine-to-app messages that embedders
+    /// poll separately from compositor repaint traffic.
+    pub fn get_events(&mut self) -> Vec<(Option<BrowserId>, EmbedderMsg)> {
+        self.embedder_receiver.receiver.try_iter().collect()
+    }
+
     pub fn handle_events(&mut self, events: Vec<WindowEvent>) -> bool {
         self.compositor.handle_events(events)
     }
@@ -274,6 +334,48 @@ impl<Window> Servo<Window> where Window: WindowMethods + 'static {
     }
 }
 
+/// Install a panic hook that captures the panicking thread's name and the
+/// panic message, then reports it as a crash: directly as an
+/// `EmbedderMsg::Panic` when `embedder_proxy` is `Some` (the privileged/UI
+/// process created by `Servo::new`), or as a high-severity (`error!`) log
+/// record routed through the constellation's logging path otherwise
+/// (content processes, which have no `EmbedderProxy` of their own).
+///
+/// A panic raised inside a panic hook aborts the process, and `catch_unwind`
+/// cannot stop that. `EmbedderProxy::send` unwraps its channel send, so the
+/// hook does not call it: it sends on the proxy's channel directly and, if
+/// the embedder's receiver is gone, silently gives up, leaving the stderr
+/// output from the default hook as the only record. The platform's default
+/// panic hook still runs first, so that stderr output and abort-on-panic
+/// behavior are unaffected.
+fn install_crash_reporter(embedder_proxy: Option<EmbedderProxy>) {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+
+        let thread_name = thread::current().name().unwrap_or("<unknown>").to_string();
+        let payload = info.payload().downcast_ref::<&str>().map(|s| s.to_string())
+            .or_else(|| info.payload().downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "<unknown panic payload>".to_string());
+        let message = match info.location() {
+            Some(location) => format!("{} ({}:{})", payload, location.file(), location.line()),
+            None => payload,
+        };
+
+        match embedder_proxy {
+            Some(ref embedder_proxy) => {
+                let report = (None, EmbedderMsg::Panic(thread_name, message));
+                if embedder_proxy.sender.send(report).is_ok() {
+                    embedder_proxy.event_loop_waker.wake();
+                }
+            }
+            None => {
+                error!("Thread \"{}\" panicked: {}", thread_name, message);
+            }
+        }
+    }));
+}
+
 fn create_compositor_channel(event_loop_waker: Box<compositor_thread::EventLoopWaker>)
     -> (CompositorProxy, CompositorReceiver) {
     let (sender, receiver) = channel();
@@ -286,6 +388,18 @@ fn create_compositor_channel(event_loop_waker: Box<compositor_thread::EventLoopW
      })
 }
 
+fn create_embedder_channel(event_loop_waker: Box<compositor_thread::EventLoopWaker>)
+    -> (EmbedderProxy, EmbedderReceiver) {
+    let (sender, receiver) = channel();
+    (EmbedderProxy {
+         sender: sender,
+         event_loop_waker: event_loop_waker,
+        },
+     EmbedderReceiver {
+         receiver: receiver
+     })
+}
+
 fn create_constellation(user_agent: Cow<'static, str>,
                         config_dir: Option<PathBuf>,
                         compositor_proxy: CompositorProxy,
@@ -328,6 +442,9 @@ fn create_constellation(user_agent: Cow<'static, str>,
     };
 
     // Initialize WebGL Thread entry point.
+    //
+    // WebGL commands always run on this dedicated WebGL thread; there is no
+    // mode that runs them on the main/UI thread.
     let (webgl_threads, image_handler) = WebGLThreads::new(gl_factory,
                                                            webrender_api_sender.clone(),
                                                            webvr_compositor.map(|c| c as Box<_>));
@@ -408,6 +525,11 @@ pub fn run_content_process(token: String) {
     PREFS.extend(unprivileged_content.prefs());
     set_logger(unprivileged_content.script_to_constellation_chan().clone());
 
+    // Content processes have no `EmbedderProxy` of their own, so a panic
+    // here is reported via `error!()` and the constellation's logging path
+    // instead of a direct `EmbedderMsg::Panic`.
+    install_crash_reporter(None);
+
     // Enter the sandbox if necessary.
     if opts::get().sandbox {
        create_sandbox();
@@ -443,3 +565,41 @@ fn create_sandbox() {
 fn create_sandbox() {
     panic!("Sandboxing is not supported on Windows or iOS.");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct NoopEventLoopWaker;
+
+    impl compositor_thread::EventLoopWaker for NoopEventLoopWaker {
+        fn wake(&self) {}
+        fn clone(&self) -> Box<compositor_thread::EventLoopWaker + Send> {
+            Box::new(NoopEventLoopWaker)
+        }
+    }
+
+    // A panicking thread whose `EmbedderProxy` channel is already
+    // disconnected must still unwind normally instead of aborting the
+    // process: the reporting send fails, and `install_crash_reporter` ignores
+    // that failure rather than panicking inside the hook.
+    #[test]
+    fn crash_reporter_survives_a_dead_embedder_channel() {
+        let (sender, receiver) = channel();
+        let embedder_proxy = EmbedderProxy {
+            sender: sender,
+            event_loop_waker: Box::new(NoopEventLoopWaker),
+        };
+        drop(receiver);
+
+        install_crash_reporter(Some(embedder_proxy));
+
+        let result = thread::Builder::new()
+            .name("crash-reporter-test-thread".to_string())
+            .spawn(|| panic!("simulated crash"))
+            .unwrap()
+            .join();
+
+        assert!(result.is_err(), "the spawned thread's own panic should still unwind normally");
+    }
+}